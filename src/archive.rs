@@ -0,0 +1,81 @@
+use std::io::Cursor;
+use std::path::Path;
+
+/// Archive formats we know how to unpack an interpreter distribution from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    TarGz,
+    TarZst,
+}
+
+impl ArchiveKind {
+    /// Infer the archive kind from an asset file name, e.g. the tail of a
+    /// download URL.
+    pub fn from_file_name(name: &str) -> anyhow::Result<Self> {
+        if name.ends_with(".zip") {
+            Ok(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(Self::TarGz)
+        } else if name.ends_with(".tar.zst") {
+            Ok(Self::TarZst)
+        } else {
+            anyhow::bail!("unrecognized archive format: {name}")
+        }
+    }
+}
+
+/// Extract `bytes` (in the format given by `kind`) into `dest_dir`.
+pub fn extract(kind: ArchiveKind, bytes: bytes::Bytes, dest_dir: &Path) -> anyhow::Result<()> {
+    match kind {
+        ArchiveKind::Zip => {
+            zip::ZipArchive::new(Cursor::new(bytes))?.extract(dest_dir)?;
+        }
+        ArchiveKind::TarGz => {
+            let decoder = flate2::read::GzDecoder::new(Cursor::new(bytes));
+            tar::Archive::new(decoder).unpack(dest_dir)?;
+        }
+        ArchiveKind::TarZst => {
+            let decoder = zstd::stream::read::Decoder::new(Cursor::new(bytes))?;
+            tar::Archive::new(decoder).unpack(dest_dir)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_file_name_recognizes_known_extensions() {
+        assert_eq!(
+            ArchiveKind::from_file_name("python-3.11.8-embed-amd64.zip").unwrap(),
+            ArchiveKind::Zip
+        );
+        assert_eq!(
+            ArchiveKind::from_file_name(
+                "cpython-3.11.8+20240224-x86_64-unknown-linux-gnu-install_only.tar.gz"
+            )
+            .unwrap(),
+            ArchiveKind::TarGz
+        );
+        assert_eq!(
+            ArchiveKind::from_file_name("something.tgz").unwrap(),
+            ArchiveKind::TarGz
+        );
+        assert_eq!(
+            ArchiveKind::from_file_name(
+                "cpython-3.11.8+20240224-aarch64-apple-darwin-install_only.tar.zst"
+            )
+            .unwrap(),
+            ArchiveKind::TarZst
+        );
+    }
+
+    #[test]
+    fn from_file_name_rejects_unknown_extensions() {
+        assert!(ArchiveKind::from_file_name("interpreter.exe").is_err());
+    }
+}
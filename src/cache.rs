@@ -0,0 +1,95 @@
+use anyhow::anyhow;
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Overrides the platform cache directory when set.
+const CACHE_DIR_ENV_VAR: &str = "EMBED_PY_CACHE_DIR";
+
+/// Directory archives are cached under, honoring `EMBED_PY_CACHE_DIR` and
+/// falling back to the platform cache dir (e.g. `~/.cache/embed-py-env` on
+/// Linux).
+pub fn cache_dir() -> anyhow::Result<PathBuf> {
+    if let Some(dir) = std::env::var_os(CACHE_DIR_ENV_VAR) {
+        return Ok(PathBuf::from(dir));
+    }
+
+    dirs::cache_dir()
+        .map(|dir| dir.join("embed-py-env"))
+        .ok_or_else(|| anyhow!("could not determine platform cache directory"))
+}
+
+/// Fetch `url`'s bytes through the content-addressed download cache,
+/// downloading only on a cache miss or a failed integrity check.
+pub async fn fetch(url: &Url) -> anyhow::Result<bytes::Bytes> {
+    let dir = cache_dir()?;
+    std::fs::create_dir_all(&dir)?;
+
+    let key = cache_key(url);
+    let entry_path = dir.join(&key);
+    let digest_path = dir.join(format!("{key}.sha256"));
+
+    if let Some(cached) = read_cached(&entry_path, &digest_path)? {
+        eprintln!("Using cached download: {url}");
+        return Ok(cached);
+    }
+
+    eprintln!("Downloading: {url}");
+    let bytes = reqwest::get(url.clone())
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    write_cached(&dir, &key, &bytes)?;
+
+    Ok(bytes)
+}
+
+/// Like [`fetch`], but also returns the SHA-256 of the bytes, for recording
+/// provenance in the distribution manifest.
+pub async fn fetch_with_digest(url: &Url) -> anyhow::Result<(bytes::Bytes, String)> {
+    let bytes = fetch(url).await?;
+    let digest = sha256_hex(&bytes);
+
+    Ok((bytes, digest))
+}
+
+/// Cache key for `url`: the SHA-256 of the URL string itself, so the same
+/// archive is shared across invocations regardless of output directory.
+fn cache_key(url: &Url) -> String {
+    sha256_hex(url.as_str().as_bytes())
+}
+
+fn read_cached(entry_path: &Path, digest_path: &Path) -> anyhow::Result<Option<bytes::Bytes>> {
+    if !entry_path.is_file() || !digest_path.is_file() {
+        return Ok(None);
+    }
+
+    let bytes = std::fs::read(entry_path)?;
+    let expected_digest = std::fs::read_to_string(digest_path)?;
+
+    if sha256_hex(&bytes) != expected_digest.trim() {
+        eprintln!("Cached entry failed integrity check, re-downloading: {entry_path:?}");
+        return Ok(None);
+    }
+
+    Ok(Some(bytes::Bytes::from(bytes)))
+}
+
+/// Write `bytes` into the cache under `key`, downloading to a temp file and
+/// renaming it into place so an interrupted write never leaves a corrupt
+/// entry behind.
+fn write_cached(dir: &Path, key: &str, bytes: &bytes::Bytes) -> anyhow::Result<()> {
+    let tmp_path = dir.join(format!("{key}.tmp-{}", std::process::id()));
+    std::fs::write(&tmp_path, bytes)?;
+    std::fs::rename(&tmp_path, dir.join(key))?;
+    std::fs::write(dir.join(format!("{key}.sha256")), sha256_hex(bytes))?;
+
+    Ok(())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    hex::encode(hasher.finalize())
+}
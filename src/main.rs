@@ -8,9 +8,24 @@ use std::{
 use structopt::StructOpt;
 use tokio::process::Command;
 
+mod archive;
+mod cache;
+mod manifest;
+mod optimize;
+mod pip;
+mod prune;
+mod target;
+mod version;
+
+use archive::ArchiveKind;
+use target::HostTarget;
+use version::PyVerTuple;
+
 #[derive(Debug, StructOpt)]
 struct Opt {
-    /// Python version (e.g. 3.9.7)
+    /// Python version (e.g. 3.9.7, or a partial specifier like 3.9).
+    /// Falls back to the nearest `.python-version` file, then the host
+    /// `python` on PATH.
     #[structopt(short, long)]
     py_version: Option<String>,
 
@@ -21,107 +36,173 @@ struct Opt {
     /// Pip requirements file
     #[structopt(short, long, parse(from_os_str))]
     requirements: Option<PathBuf>,
+
+    /// Make the env exactly match the requirements file, uninstalling
+    /// anything not listed instead of only adding to it
+    #[structopt(long)]
+    sync: bool,
+
+    /// Compile installed code to bytecode at this optimization level
+    /// (0, 1, or 2) and strip `.py` sources that compiled successfully
+    #[structopt(long)]
+    optimize: Option<u8>,
+
+    /// Keep `.py` sources when using --optimize instead of stripping them
+    #[structopt(long)]
+    keep_sources: bool,
+
+    /// Prune the stdlib, removing the default exclusion set plus any
+    /// --exclude patterns
+    #[structopt(long)]
+    prune: bool,
+
+    /// Glob or bare file/directory name to prune (repeatable); combines
+    /// with the built-in default exclusion set. A bare name matches only a
+    /// path component equal to it (e.g. the `tkinter` package directory),
+    /// so a module file needs its extension (`turtle.py`, not `turtle`)
+    #[structopt(long = "exclude")]
+    exclude: Vec<String>,
+
+    /// Print what --prune would remove instead of removing it
+    #[structopt(long)]
+    prune_dry_run: bool,
+
+    /// Emit manifest.json and THIRD_PARTY_LICENSES describing the
+    /// assembled distribution
+    #[structopt(long)]
+    manifest: bool,
 }
 
 const GET_PIP_URL: &str = "https://bootstrap.pypa.io/get-pip.py";
 
-type PyVerTuple = (u16, u16, u16);
+/// Pinned `python-build-standalone` release used to source non-Windows
+/// interpreter builds. Bumping this requires updating `version::KNOWN_VERSIONS`
+/// to the exact micro releases the new tag ships, or asset names won't exist.
+const PYTHON_BUILD_STANDALONE_RELEASE_TAG: &str = "20240224";
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let opt = Opt::from_args();
-    let py_version = match opt.py_version {
-        Some(ver) => python_version_from_str(&ver)?,
-        None => default_python_version().await?,
-    };
+    let py_version = version::resolve(opt.py_version.as_deref(), &env::current_dir()?).await?;
 
-    let pip_path = opt.output_dir.join("Scripts").join("pip.exe");
+    let target = HostTarget::detect()?;
+    let pip_path = opt
+        .output_dir
+        .join(target.scripts_dir_name())
+        .join(target.pip_file_name());
 
-    if !pip_path.is_file() {
-        create_embedded_env(&py_version, &opt.output_dir).await?;
+    let source_info = if !pip_path.is_file() {
+        Some(create_embedded_env(&py_version, target, &opt.output_dir).await?)
     } else {
         eprintln!("Using existing env: {:?}", opt.output_dir);
-    }
+        None
+    };
 
-    if let Some(reqs_path) = opt.requirements {
-        install_requirements(&pip_path, &opt.output_dir, &reqs_path).await?;
+    if let Some(reqs_path) = &opt.requirements {
+        if opt.sync {
+            pip::sync(&pip_path, target, &opt.output_dir, reqs_path).await?;
+        } else {
+            pip::install(&pip_path, target, &opt.output_dir, reqs_path).await?;
+        }
     }
 
-    eprintln!("Done!");
-    Ok(())
-}
-
-async fn install_requirements(
-    pip_path: &Path,
-    dist_dir: &Path,
-    reqs_path: &Path,
-) -> anyhow::Result<()> {
-    eprintln!("Installing pip requirements...");
-
-    let path_var = dist_env_path(dist_dir);
-
-    let out = Command::new(pip_path.to_string_lossy().as_ref())
-        .env("PATH", &path_var)
-        .arg("install")
-        .arg("-r")
-        .arg(reqs_path.to_string_lossy().as_ref())
-        .output()
-        .await?;
+    if let Some(level) = opt.optimize {
+        optimize::optimize(target, &opt.output_dir, level, !opt.keep_sources).await?;
+    }
 
-    eprintln!("pip stdout: {}", String::from_utf8_lossy(&out.stdout));
-    eprintln!("pip stderr: {}", String::from_utf8_lossy(&out.stderr));
+    if opt.prune || opt.prune_dry_run {
+        let mut patterns: Vec<String> = prune::DEFAULT_EXCLUDES
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        patterns.extend(opt.exclude.iter().cloned());
+
+        let required_dist_names = match &opt.requirements {
+            Some(reqs_path) => pip::required_names(reqs_path)?,
+            None => Default::default(),
+        };
+        let protected = prune::protected_import_names(&opt.output_dir, &required_dist_names)?;
+
+        let removed = prune::prune(&opt.output_dir, &patterns, &protected, opt.prune_dry_run)?;
+        if !opt.prune_dry_run {
+            eprintln!("Pruned {} path(s)", removed.len());
+        }
+    }
 
-    if !out.status.success() {
-        bail!("pip failed");
+    if opt.manifest {
+        manifest::write(&opt.output_dir, &py_version, source_info)?;
     }
 
+    eprintln!("Done!");
     Ok(())
 }
 
-async fn create_embedded_env(py_version: &PyVerTuple, dist_dir: &Path) -> anyhow::Result<()> {
-    let libs_dir_src = host_python_dir(&py_version)?;
+async fn create_embedded_env(
+    py_version: &PyVerTuple,
+    target: HostTarget,
+    dist_dir: &Path,
+) -> anyhow::Result<manifest::SourceInfo> {
     let get_pip_path = dist_dir.join("get-pip.py");
 
     fs::create_dir_all(&dist_dir)?;
 
-    // Download embedded zip file
-    eprintln!("Downloading zip file...");
-    {
-        let py_zip = reqwest::get(python_embed_zip_url(py_version)?)
-            .await?
-            .bytes()
-            .await?;
-
-        zip::ZipArchive::new(Cursor::new(py_zip))?.extract(dist_dir)?;
-    }
+    let source_info = if target.is_windows_embed() {
+        let libs_dir_src = host_python_dir(py_version)?;
+        let url = python_embed_zip_url(py_version)?;
+
+        // Download embedded zip file
+        eprintln!("Downloading zip file...");
+        let (py_zip, sha256) = cache::fetch_with_digest(&url).await?;
+        archive::extract(ArchiveKind::Zip, py_zip, dist_dir)?;
+
+        // Copy libs
+        eprintln!("Copying libs...");
+        {
+            fs_extra::dir::copy(
+                libs_dir_src,
+                &dist_dir,
+                &fs_extra::dir::CopyOptions {
+                    skip_exist: true,
+                    depth: 1,
+                    ..Default::default()
+                },
+            )?;
+        }
 
-    // Copy libs
-    eprintln!("Copying libs...");
-    {
-        fs_extra::dir::copy(
-            libs_dir_src,
-            &dist_dir,
-            &fs_extra::dir::CopyOptions {
-                skip_exist: true,
-                depth: 1,
-                ..Default::default()
-            },
-        )?;
-    }
+        // Enable site
+        eprintln!("Enabling import site...");
+        {
+            let pth_path = dist_dir.join(format!("python{}{}._pth", py_version.0, py_version.1));
+            let contents = fs::read_to_string(&pth_path)?;
+            let contents = contents.replace("#import site", "import site");
+            fs::write(&pth_path, contents)?;
+        }
 
-    // Enable site
-    eprintln!("Enabling import site...");
-    {
-        let pth_path = dist_dir.join(format!("python{}{}._pth", py_version.0, py_version.1));
-        let contents = fs::read_to_string(&pth_path)?;
-        let contents = contents.replace("#import site", "import site");
-        fs::write(&pth_path, contents)?;
-    }
+        manifest::SourceInfo {
+            url: url.to_string(),
+            sha256,
+        }
+    } else {
+        // Download and extract a redistributable standalone build. These
+        // already ship with a working site layout, so there's no `._pth`
+        // step to patch.
+        eprintln!("Downloading standalone build...");
+        let (url, asset_name) = python_standalone_url(py_version, target)?;
+        let (archive_bytes, sha256) = cache::fetch_with_digest(&url).await?;
+        let kind = ArchiveKind::from_file_name(&asset_name)?;
+
+        archive::extract(kind, archive_bytes, dist_dir)?;
+
+        manifest::SourceInfo {
+            url: url.to_string(),
+            sha256,
+        }
+    };
 
     // Download get-pip.py
     eprintln!("Downloading get-pip...");
     {
-        let bytes = reqwest::get(GET_PIP_URL).await?.bytes().await?;
+        let bytes = cache::fetch(&Url::parse(GET_PIP_URL)?).await?;
         let mut content = Cursor::new(bytes);
         let mut file = fs::File::create(&get_pip_path)?;
         io::copy(&mut content, &mut file)?;
@@ -130,8 +211,8 @@ async fn create_embedded_env(py_version: &PyVerTuple, dist_dir: &Path) -> anyhow
     // Install pip
     eprintln!("Installing pip...");
     {
-        let path_var = dist_env_path(dist_dir);
-        let python_bin = dist_dir.join("python");
+        let path_var = target.dist_env_path(dist_dir);
+        let python_bin = dist_dir.join(target.python_file_name());
 
         let out = Command::new(python_bin.to_string_lossy().as_ref())
             .env("PATH", &path_var)
@@ -150,45 +231,7 @@ async fn create_embedded_env(py_version: &PyVerTuple, dist_dir: &Path) -> anyhow
         // fs::remove_file(&get_pip_path)?;
     }
 
-    Ok(())
-}
-
-fn dist_env_path(dist_dir: &Path) -> String {
-    let scripts_dir = dist_dir.join("Scripts");
-    format!(
-        "{}:{}",
-        dist_dir.to_string_lossy(),
-        scripts_dir.to_string_lossy()
-    )
-}
-
-async fn default_python_version() -> anyhow::Result<PyVerTuple> {
-    let out = Command::new("python")
-        .args(&[
-            "-c",
-            "import sys; print('.'.join(str(x) for x in sys.version_info[:3]))",
-        ])
-        .output()
-        .await?;
-
-    let ver_str = String::from_utf8_lossy(&out.stdout);
-
-    python_version_from_str(&ver_str)
-}
-
-fn python_version_from_str(s: &str) -> anyhow::Result<PyVerTuple> {
-    let tuple = match s
-        .trim()
-        .split('.')
-        .into_iter()
-        .collect::<Vec<_>>()
-        .as_slice()
-    {
-        &[major, minor, micro] => (major.parse()?, minor.parse()?, micro.parse()?),
-        _ => bail!("Version must be of the format: 1.2.3"),
-    };
-
-    Ok(tuple)
+    Ok(source_info)
 }
 
 fn python_embed_zip_url(version: &PyVerTuple) -> anyhow::Result<Url> {
@@ -198,6 +241,24 @@ fn python_embed_zip_url(version: &PyVerTuple) -> anyhow::Result<Url> {
     ))?)
 }
 
+/// URL and asset file name of the `python-build-standalone` release asset
+/// for `version`/`target`, e.g.
+/// `cpython-3.9.18+20240224-aarch64-apple-darwin-install_only.tar.zst`.
+fn python_standalone_url(version: &PyVerTuple, target: HostTarget) -> anyhow::Result<(Url, String)> {
+    let triple = target.standalone_triple()?;
+    let tag = PYTHON_BUILD_STANDALONE_RELEASE_TAG;
+    let asset_name = format!(
+        "cpython-{0}.{1}.{2}+{tag}-{triple}-install_only.tar.zst",
+        version.0, version.1, version.2
+    );
+
+    let url = Url::parse(&format!(
+        "https://github.com/indygreg/python-build-standalone/releases/download/{tag}/{asset_name}"
+    ))?;
+
+    Ok((url, asset_name))
+}
+
 fn host_python_dir(version: &PyVerTuple) -> anyhow::Result<PathBuf> {
     let target_py = format!("Python{}{}", version.0, version.1);
     let env_path = env::var_os("PATH").ok_or_else(|| anyhow!("missing PATH env"))?;
@@ -0,0 +1,167 @@
+use anyhow::Context;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use walkdir::WalkDir;
+
+use crate::version::PyVerTuple;
+
+/// Provenance of the interpreter source archive the dist was built from.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceInfo {
+    pub url: String,
+    pub sha256: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Manifest {
+    python_version: String,
+    source: Option<SourceInfo>,
+    distributions: Vec<DistributionInfo>,
+    files: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DistributionInfo {
+    name: String,
+    version: String,
+    licenses: Vec<String>,
+}
+
+/// Write `manifest.json` and `THIRD_PARTY_LICENSES` describing the
+/// assembled distribution under `dist_dir`: Python version, source archive
+/// provenance (when known), installed distributions with their license
+/// metadata, and a recursive SHA-256 of every file in the dist.
+pub fn write(
+    dist_dir: &Path,
+    py_version: &PyVerTuple,
+    source: Option<SourceInfo>,
+) -> anyhow::Result<()> {
+    eprintln!("Building distribution manifest...");
+
+    let distributions = collect_distributions(dist_dir)?;
+    let files = hash_files(dist_dir)?;
+
+    let manifest = Manifest {
+        python_version: format!("{}.{}.{}", py_version.0, py_version.1, py_version.2),
+        source,
+        distributions: distributions.clone(),
+        files,
+    };
+
+    std::fs::write(
+        dist_dir.join("manifest.json"),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+
+    write_third_party_licenses(dist_dir, &distributions)?;
+
+    Ok(())
+}
+
+fn hash_files(dist_dir: &Path) -> anyhow::Result<BTreeMap<String, String>> {
+    let mut files = BTreeMap::new();
+
+    for entry in WalkDir::new(dist_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+    {
+        let rel = entry
+            .path()
+            .strip_prefix(dist_dir)
+            .unwrap_or(entry.path())
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        let bytes = std::fs::read(entry.path())?;
+        let mut hasher = Sha256::new();
+        hasher.update(&bytes);
+        files.insert(rel, hex::encode(hasher.finalize()));
+    }
+
+    Ok(files)
+}
+
+/// Find every `*.dist-info` directory under `dist_dir` and read its name,
+/// version, and license metadata.
+fn collect_distributions(dist_dir: &Path) -> anyhow::Result<Vec<DistributionInfo>> {
+    let mut distributions = WalkDir::new(dist_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_dir())
+        .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "dist-info"))
+        .map(|entry| read_dist_info(entry.path()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    distributions.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(distributions)
+}
+
+fn read_dist_info(dir: &Path) -> anyhow::Result<DistributionInfo> {
+    let metadata_path = dir.join("METADATA");
+    let contents = std::fs::read_to_string(&metadata_path)
+        .with_context(|| format!("reading {metadata_path:?}"))?;
+
+    let mut name = String::new();
+    let mut version = String::new();
+    let mut licenses = Vec::new();
+
+    for line in contents.lines() {
+        if let Some(value) = line.strip_prefix("Name: ") {
+            name = value.trim().to_string();
+        } else if let Some(value) = line.strip_prefix("Version: ") {
+            version = value.trim().to_string();
+        } else if let Some(value) = line.strip_prefix("License: ") {
+            let value = value.trim();
+            if !value.is_empty() && value != "UNKNOWN" {
+                licenses.push(value.to_string());
+            }
+        } else if let Some(value) = line.strip_prefix("Classifier: License ") {
+            licenses.push(value.trim().to_string());
+        }
+    }
+
+    // Also note every bundled LICENSE*/COPYING* file, alongside whatever
+    // license fields METADATA carried.
+    for entry in std::fs::read_dir(dir)? {
+        let file_name = entry?.file_name();
+        let file_name = file_name.to_string_lossy();
+        if file_name.starts_with("LICENSE") || file_name.starts_with("COPYING") {
+            licenses.push(format!("bundled: {file_name}"));
+        }
+    }
+
+    licenses.sort();
+    licenses.dedup();
+
+    Ok(DistributionInfo {
+        name,
+        version,
+        licenses,
+    })
+}
+
+fn write_third_party_licenses(dist_dir: &Path, distributions: &[DistributionInfo]) -> anyhow::Result<()> {
+    let mut out = String::new();
+
+    for dist in distributions {
+        out.push_str(&format!("{} {}\n", dist.name, dist.version));
+
+        if dist.licenses.is_empty() {
+            out.push_str("  (no license metadata found)\n");
+        } else {
+            for license in &dist.licenses {
+                out.push_str(&format!("  {license}\n"));
+            }
+        }
+
+        out.push('\n');
+    }
+
+    std::fs::write(dist_dir.join("THIRD_PARTY_LICENSES"), out)?;
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use anyhow::bail;
+use std::path::Path;
+use tokio::process::Command;
+use walkdir::WalkDir;
+
+use crate::target::HostTarget;
+
+/// Compile every `.py` under `dist_dir` to bytecode at `opt_level` (passed
+/// straight through to `compileall -o`), optionally stripping `.py` sources
+/// that now have a matching `.pyc` next to them.
+pub async fn optimize(
+    target: HostTarget,
+    dist_dir: &Path,
+    opt_level: u8,
+    strip_sources: bool,
+) -> anyhow::Result<()> {
+    if opt_level > 2 {
+        bail!("--optimize level must be 0, 1, or 2, got {opt_level}");
+    }
+
+    eprintln!("Precompiling to bytecode (level {opt_level})...");
+
+    let before_size = dir_size(dist_dir)?;
+    let python_bin = dist_dir.join(target.python_file_name());
+
+    // `-b` writes `.pyc` next to the module (the legacy, non-`__pycache__`
+    // location) so the dist stays importable without sources.
+    let out = Command::new(python_bin.to_string_lossy().as_ref())
+        .args(&["-m", "compileall", "-q", "-b", "-o", &opt_level.to_string()])
+        .arg(dist_dir)
+        .output()
+        .await?;
+
+    eprintln!("compileall stdout: {}", String::from_utf8_lossy(&out.stdout));
+    eprintln!("compileall stderr: {}", String::from_utf8_lossy(&out.stderr));
+
+    if !out.status.success() {
+        bail!("compileall failed");
+    }
+
+    if strip_sources {
+        let removed = strip_compiled_sources(dist_dir)?;
+        eprintln!("Removed {removed} source file(s) with compiled bytecode");
+    }
+
+    let after_size = dir_size(dist_dir)?;
+    eprintln!(
+        "Dist size: {before_size} -> {after_size} bytes ({} saved)",
+        before_size.saturating_sub(after_size)
+    );
+
+    Ok(())
+}
+
+/// Delete `.py` files that have a sibling `.pyc` (left by `-b`), keeping
+/// anything `compileall` failed to compile.
+fn strip_compiled_sources(dist_dir: &Path) -> anyhow::Result<usize> {
+    let mut removed = 0;
+
+    for entry in WalkDir::new(dist_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+    {
+        let path = entry.path();
+        if path.extension().map_or(false, |ext| ext == "py") && path.with_extension("pyc").is_file() {
+            std::fs::remove_file(path)?;
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+fn dir_size(dir: &Path) -> anyhow::Result<u64> {
+    let mut total = 0;
+
+    for entry in WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+    {
+        total += entry.metadata()?.len();
+    }
+
+    Ok(total)
+}
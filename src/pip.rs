@@ -0,0 +1,258 @@
+use anyhow::bail;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tokio::process::Command;
+
+use crate::target::HostTarget;
+
+#[derive(Debug, Deserialize)]
+struct InstalledDist {
+    name: String,
+}
+
+/// Never uninstalled by `sync`, even when absent from the requirements
+/// file: these are pip's own bootstrap dependencies (installed by
+/// get-pip), not something a requirements file would normally pin.
+const SYNC_PROTECTED: &[&str] = &["pip", "setuptools", "wheel"];
+
+/// Run `pip install -r <reqs_path>` against the embedded interpreter.
+pub async fn install(
+    pip_path: &Path,
+    target: HostTarget,
+    dist_dir: &Path,
+    reqs_path: &Path,
+) -> anyhow::Result<()> {
+    eprintln!("Installing pip requirements...");
+
+    run_pip(
+        pip_path,
+        target,
+        dist_dir,
+        &["install", "-r", &reqs_path.to_string_lossy()],
+    )
+    .await
+}
+
+/// Make the env exactly match `reqs_path`: uninstall anything installed
+/// that the requirements file no longer lists (directly or transitively),
+/// then install/upgrade the rest.
+pub async fn sync(
+    pip_path: &Path,
+    target: HostTarget,
+    dist_dir: &Path,
+    reqs_path: &Path,
+) -> anyhow::Result<()> {
+    eprintln!("Syncing env to requirements...");
+
+    let required = required_names(reqs_path)?;
+    let installed = installed_names(pip_path, target, dist_dir).await?;
+    let keep = transitive_closure(pip_path, target, dist_dir, &required, &installed).await?;
+
+    let extraneous = installed
+        .iter()
+        .filter(|name| !keep.contains(*name) && !SYNC_PROTECTED.contains(&name.as_str()))
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+
+    if !extraneous.is_empty() {
+        eprintln!("Uninstalling extraneous packages: {:?}", extraneous);
+
+        let mut args = vec!["uninstall", "-y"];
+        args.extend(extraneous);
+        run_pip(pip_path, target, dist_dir, &args).await?;
+    }
+
+    install(pip_path, target, dist_dir, reqs_path).await
+}
+
+/// Expand `required` to include every package reachable through
+/// `installed`'s declared dependencies, so `sync` never uninstalls a
+/// transitive dependency of something the requirements file lists (e.g.
+/// `werkzeug` when the file only pins `flask`).
+async fn transitive_closure(
+    pip_path: &Path,
+    target: HostTarget,
+    dist_dir: &Path,
+    required: &HashSet<String>,
+    installed: &HashSet<String>,
+) -> anyhow::Result<HashSet<String>> {
+    let graph = dependency_graph(pip_path, target, dist_dir, installed).await?;
+
+    let mut keep = HashSet::new();
+    let mut stack: Vec<String> = required.iter().cloned().collect();
+
+    while let Some(name) = stack.pop() {
+        if !keep.insert(name.clone()) {
+            continue;
+        }
+
+        if let Some(deps) = graph.get(&name) {
+            stack.extend(deps.iter().cloned());
+        }
+    }
+
+    Ok(keep)
+}
+
+/// Map of normalized distribution name -> normalized names of its declared
+/// dependencies, read from `pip show`'s `Requires:` line for every dist in
+/// `names`.
+async fn dependency_graph(
+    pip_path: &Path,
+    target: HostTarget,
+    dist_dir: &Path,
+    names: &HashSet<String>,
+) -> anyhow::Result<HashMap<String, Vec<String>>> {
+    if names.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let path_var = target.dist_env_path(dist_dir);
+
+    let mut args = vec!["show".to_string()];
+    args.extend(names.iter().cloned());
+
+    let out = Command::new(pip_path.to_string_lossy().as_ref())
+        .env("PATH", &path_var)
+        .args(&args)
+        .output()
+        .await?;
+
+    if !out.status.success() {
+        bail!("pip show failed: {}", String::from_utf8_lossy(&out.stderr));
+    }
+
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let mut graph = HashMap::new();
+    let mut current_name: Option<String> = None;
+
+    for line in stdout.lines() {
+        if let Some(value) = line.strip_prefix("Name: ") {
+            current_name = Some(normalize_name(value));
+        } else if let Some(value) = line.strip_prefix("Requires: ") {
+            if let Some(name) = &current_name {
+                let deps = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|dep| !dep.is_empty())
+                    .map(normalize_name)
+                    .collect();
+                graph.insert(name.clone(), deps);
+            }
+        }
+    }
+
+    Ok(graph)
+}
+
+/// Distribution names currently installed in `dist_dir`, normalized for
+/// comparison against `required_names`.
+async fn installed_names(
+    pip_path: &Path,
+    target: HostTarget,
+    dist_dir: &Path,
+) -> anyhow::Result<HashSet<String>> {
+    let path_var = target.dist_env_path(dist_dir);
+
+    let out = Command::new(pip_path.to_string_lossy().as_ref())
+        .env("PATH", &path_var)
+        .args(&["list", "--format=json"])
+        .output()
+        .await?;
+
+    if !out.status.success() {
+        bail!("pip list failed: {}", String::from_utf8_lossy(&out.stderr));
+    }
+
+    let dists: Vec<InstalledDist> = serde_json::from_slice(&out.stdout)?;
+
+    Ok(dists.into_iter().map(|d| normalize_name(&d.name)).collect())
+}
+
+/// Parse the top-level package names out of a requirements file, ignoring
+/// version specifiers, environment markers, comments and options (`-r`,
+/// `--hash`, etc).
+pub fn required_names(reqs_path: &Path) -> anyhow::Result<HashSet<String>> {
+    let contents = std::fs::read_to_string(reqs_path)?;
+
+    let names = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('-'))
+        .filter_map(|line| line.split(|c: char| "=<>!~;[ ".contains(c)).next())
+        .map(normalize_name)
+        .collect();
+
+    Ok(names)
+}
+
+/// Normalize a package name the way pip does for comparisons: lowercase
+/// with `_`/`.` folded to `-`.
+pub(crate) fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase().replace(['_', '.'], "-")
+}
+
+async fn run_pip(
+    pip_path: &Path,
+    target: HostTarget,
+    dist_dir: &Path,
+    args: &[&str],
+) -> anyhow::Result<()> {
+    let path_var = target.dist_env_path(dist_dir);
+
+    let out = Command::new(pip_path.to_string_lossy().as_ref())
+        .env("PATH", &path_var)
+        .args(args)
+        .output()
+        .await?;
+
+    eprintln!("pip stdout: {}", String::from_utf8_lossy(&out.stdout));
+    eprintln!("pip stderr: {}", String::from_utf8_lossy(&out.stderr));
+
+    if !out.status.success() {
+        bail!("pip failed");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_name_folds_case_and_separators() {
+        assert_eq!(normalize_name("PyYAML"), "pyyaml");
+        assert_eq!(normalize_name("typing_extensions"), "typing-extensions");
+        assert_eq!(normalize_name("zope.interface"), "zope-interface");
+    }
+
+    #[test]
+    fn required_names_ignores_specifiers_markers_comments_and_options() {
+        let dir = std::env::temp_dir().join(format!("embed-py-env-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let reqs_path = dir.join("requirements.txt");
+        std::fs::write(
+            &reqs_path,
+            "\
+# a comment
+-r other.txt
+--hash=sha256:deadbeef
+requests==2.31.0
+Flask>=2.0; python_version >= '3.8'
+typing_extensions
+",
+        )
+        .unwrap();
+
+        let names = required_names(&reqs_path).unwrap();
+
+        assert!(names.contains("requests"));
+        assert!(names.contains("flask"));
+        assert!(names.contains("typing-extensions"));
+        assert_eq!(names.len(), 3);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,184 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::pip::normalize_name;
+
+/// Packages/modules excluded by default when `--prune` is used. Most
+/// embedded apps never touch these, and they make up a large share of a
+/// standalone build's size.
+pub const DEFAULT_EXCLUDES: &[&str] = &[
+    "tkinter",
+    "test",
+    "tests",
+    "idlelib",
+    "ensurepip",
+    "distutils",
+    "lib2to3",
+    "turtledemo",
+];
+
+/// Real Python import names (e.g. `yaml`, `typing_extensions`) for the
+/// required distributions in `required_dist_names` (pip-normalized
+/// distribution names, as returned by [`crate::pip::required_names`]).
+///
+/// Resolved from each matching `*.dist-info`'s `top_level.txt`, since a
+/// distribution's import name routinely differs from its normalized
+/// package name (`PyYAML` on PyPI installs as `yaml`). Falls back to the
+/// normalized distribution name when a dist ships no `top_level.txt`.
+pub fn protected_import_names(
+    dist_dir: &Path,
+    required_dist_names: &HashSet<String>,
+) -> anyhow::Result<HashSet<String>> {
+    let mut names = HashSet::new();
+
+    for entry in WalkDir::new(dist_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_dir())
+        .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "dist-info"))
+    {
+        let dir_name = entry.file_name().to_string_lossy().into_owned();
+        let Some(dist_name) = dist_info_name(&dir_name) else {
+            continue;
+        };
+
+        if !required_dist_names.contains(&normalize_name(&dist_name)) {
+            continue;
+        }
+
+        let top_level_path = entry.path().join("top_level.txt");
+        match std::fs::read_to_string(&top_level_path) {
+            Ok(contents) => names.extend(
+                contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(String::from),
+            ),
+            Err(_) => {
+                // No top_level.txt to read the real import name from.
+                // Approximate it the way distribution names usually map to
+                // import dirs (dashes/dots -> underscores), not pip's own
+                // dash-normalized form, which `is_protected` never matches.
+                names.insert(normalize_name(&dist_name).replace('-', "_"));
+            }
+        }
+    }
+
+    Ok(names)
+}
+
+/// Distribution name out of a `*.dist-info` directory name, e.g.
+/// `PyYAML-6.0.dist-info` -> `PyYAML`.
+fn dist_info_name(dir_name: &str) -> Option<String> {
+    let stem = dir_name.strip_suffix(".dist-info")?;
+    let (name, _version) = stem.rsplit_once('-')?;
+    Some(name.to_string())
+}
+
+/// Remove files/packages under `dist_dir` matching `patterns`, refusing to
+/// remove anything whose path contains one of `protected_names` (real
+/// import names, e.g. from [`protected_import_names`]). Returns the
+/// dist-relative paths that were (or, in `dry_run`, would be) removed.
+pub fn prune(
+    dist_dir: &Path,
+    patterns: &[String],
+    protected_names: &HashSet<String>,
+    dry_run: bool,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let set = build_glob_set(patterns)?;
+    let mut removed = Vec::new();
+    let mut walker = WalkDir::new(dist_dir).into_iter();
+
+    while let Some(entry) = walker.next() {
+        let entry = entry?;
+        let rel = entry.path().strip_prefix(dist_dir).unwrap_or(entry.path());
+
+        if rel.as_os_str().is_empty() || !set.is_match(rel) {
+            continue;
+        }
+
+        if is_protected(rel, protected_names) {
+            eprintln!("Keeping protected path: {rel:?}");
+            continue;
+        }
+
+        if entry.file_type().is_dir() {
+            // Don't descend into a directory we're about to drop.
+            walker.skip_current_dir();
+        }
+
+        if dry_run {
+            eprintln!("Would remove: {rel:?}");
+        } else if entry.file_type().is_dir() {
+            std::fs::remove_dir_all(entry.path())?;
+            eprintln!("Removed: {rel:?}");
+        } else {
+            std::fs::remove_file(entry.path())?;
+            eprintln!("Removed: {rel:?}");
+        }
+
+        removed.push(rel.to_path_buf());
+    }
+
+    Ok(removed)
+}
+
+/// Build the glob set patterns are matched against, expanding bare names
+/// (no `/` or glob metacharacters, e.g. `tkinter`) into `**/tkinter` and
+/// `**/tkinter/**` so they match that path component at any depth.
+fn build_glob_set(patterns: &[String]) -> anyhow::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+
+    for pattern in patterns {
+        if pattern.contains('/') || pattern.contains(['*', '?', '[']) {
+            builder.add(Glob::new(pattern)?);
+        } else {
+            builder.add(Glob::new(&format!("**/{pattern}"))?);
+            builder.add(Glob::new(&format!("**/{pattern}/**"))?);
+        }
+    }
+
+    Ok(builder.build()?)
+}
+
+fn is_protected(rel: &Path, protected_names: &HashSet<String>) -> bool {
+    rel.components().any(|component| {
+        let name = component.as_os_str().to_string_lossy();
+        let stem = name.split('.').next().unwrap_or(&name);
+        protected_names.contains(stem)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_glob_set_matches_bare_names_at_any_depth() {
+        let set = build_glob_set(&["tkinter".to_string()]).unwrap();
+
+        assert!(set.is_match(Path::new("lib/tkinter")));
+        assert!(set.is_match(Path::new("lib/tkinter/__init__.py")));
+        assert!(!set.is_match(Path::new("lib/not_tkinter_at_all.py")));
+    }
+
+    #[test]
+    fn build_glob_set_treats_patterns_with_slashes_or_wildcards_as_globs() {
+        let set = build_glob_set(&["lib/**/*.pyc".to_string()]).unwrap();
+
+        assert!(set.is_match(Path::new("lib/foo/bar.pyc")));
+        assert!(!set.is_match(Path::new("lib/foo/bar.py")));
+    }
+
+    #[test]
+    fn dist_info_name_strips_version_and_suffix() {
+        assert_eq!(
+            dist_info_name("PyYAML-6.0.dist-info"),
+            Some("PyYAML".to_string())
+        );
+        assert_eq!(dist_info_name("not-a-dist-info-dir"), None);
+    }
+}
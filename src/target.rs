@@ -0,0 +1,91 @@
+use anyhow::{anyhow, bail};
+use std::path::Path;
+
+/// The platform we're assembling an embedded distribution for.
+///
+/// Only the host platform is supported today (no cross-building), so this is
+/// always derived from `std::env::consts`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostTarget {
+    WindowsAmd64,
+    LinuxX86_64,
+    LinuxAarch64,
+    MacosX86_64,
+    MacosAarch64,
+}
+
+impl HostTarget {
+    /// Detect the current host platform.
+    pub fn detect() -> anyhow::Result<Self> {
+        match (std::env::consts::OS, std::env::consts::ARCH) {
+            ("windows", "x86_64") => Ok(Self::WindowsAmd64),
+            ("linux", "x86_64") => Ok(Self::LinuxX86_64),
+            ("linux", "aarch64") => Ok(Self::LinuxAarch64),
+            ("macos", "x86_64") => Ok(Self::MacosX86_64),
+            ("macos", "aarch64") => Ok(Self::MacosAarch64),
+            (os, arch) => bail!("unsupported host platform: {os}-{arch}"),
+        }
+    }
+
+    /// Whether this target uses the python.org Windows embeddable zip
+    /// instead of an indygreg `python-build-standalone` release.
+    pub fn is_windows_embed(self) -> bool {
+        matches!(self, Self::WindowsAmd64)
+    }
+
+    /// The `python-build-standalone` release asset triple, e.g.
+    /// `x86_64-unknown-linux-gnu`.
+    pub fn standalone_triple(self) -> anyhow::Result<&'static str> {
+        match self {
+            Self::LinuxX86_64 => Ok("x86_64-unknown-linux-gnu"),
+            Self::LinuxAarch64 => Ok("aarch64-unknown-linux-gnu"),
+            Self::MacosX86_64 => Ok("x86_64-apple-darwin"),
+            Self::MacosAarch64 => Ok("aarch64-apple-darwin"),
+            Self::WindowsAmd64 => Err(anyhow!(
+                "windows uses the python.org embeddable zip, not python-build-standalone"
+            )),
+        }
+    }
+
+    /// Directory, relative to the dist root, where `pip` and other console
+    /// scripts are installed.
+    ///
+    /// `python-build-standalone` `install_only` archives unpack to a
+    /// top-level `python/` directory, so the Unix scripts dir is nested
+    /// under it.
+    pub fn scripts_dir_name(self) -> &'static str {
+        if self.is_windows_embed() {
+            "Scripts"
+        } else {
+            "python/bin"
+        }
+    }
+
+    /// File name of the `pip` executable within [`Self::scripts_dir_name`].
+    pub fn pip_file_name(self) -> &'static str {
+        if self.is_windows_embed() {
+            "pip.exe"
+        } else {
+            "pip"
+        }
+    }
+
+    /// File name of the `python` executable, relative to the dist root.
+    pub fn python_file_name(self) -> &'static str {
+        if self.is_windows_embed() {
+            "python.exe"
+        } else {
+            "python/bin/python3"
+        }
+    }
+
+    /// `PATH` value to run console scripts (e.g. `pip`) out of `dist_dir`.
+    pub fn dist_env_path(self, dist_dir: &Path) -> String {
+        let scripts_dir = dist_dir.join(self.scripts_dir_name());
+        format!(
+            "{}:{}",
+            dist_dir.to_string_lossy(),
+            scripts_dir.to_string_lossy()
+        )
+    }
+}
@@ -0,0 +1,145 @@
+use anyhow::{anyhow, bail};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+pub type PyVerTuple = (u16, u16, u16);
+
+/// Curated list of known CPython releases, used to resolve a partial
+/// specifier (`3.9`, `3`) to a concrete `major.minor.micro`. Ordered oldest
+/// to newest so the last match for a given major/minor is the latest micro.
+///
+/// These must stay in lockstep with `PYTHON_BUILD_STANDALONE_RELEASE_TAG`
+/// in `main.rs`: each micro here is the one that release actually shipped,
+/// since that tag is what `python_standalone_url` builds asset names from.
+const KNOWN_VERSIONS: &[PyVerTuple] = &[
+    (3, 8, 18),
+    (3, 9, 18),
+    (3, 10, 13),
+    (3, 11, 8),
+    (3, 12, 2),
+];
+
+const VERSION_FILE_NAMES: &[&str] = &[".python-version", ".python-versions"];
+
+/// Resolve the Python version to use, in order of precedence: an explicit
+/// `--py-version` flag, the nearest `.python-version` file walking up from
+/// `start_dir`, then whatever `python` is on PATH.
+pub async fn resolve(explicit: Option<&str>, start_dir: &Path) -> anyhow::Result<PyVerTuple> {
+    if let Some(ver) = explicit {
+        return python_version_from_str(ver);
+    }
+
+    if let Some(path) = find_version_file(start_dir) {
+        eprintln!("Using Python version from {:?}", path);
+        return python_version_from_file(&path);
+    }
+
+    default_python_version().await
+}
+
+/// Walk from `start_dir` up through its ancestors looking for a
+/// `.python-version` or `.python-versions` file.
+fn find_version_file(start_dir: &Path) -> Option<PathBuf> {
+    let mut dir = Some(start_dir);
+
+    while let Some(d) = dir {
+        for name in VERSION_FILE_NAMES {
+            let candidate = d.join(name);
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+        dir = d.parent();
+    }
+
+    None
+}
+
+fn python_version_from_file(path: &Path) -> anyhow::Result<PyVerTuple> {
+    let contents = std::fs::read_to_string(path)?;
+
+    let line = contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .ok_or_else(|| anyhow!("{:?} has no version entry", path))?;
+
+    python_version_from_str(line)
+}
+
+/// Probe the host `python` on PATH for a version to use, resolving it to
+/// the nearest [`KNOWN_VERSIONS`] micro rather than the host's exact
+/// micro: only the pinned micros actually exist as
+/// `python-build-standalone` release assets, so using the host's literal
+/// `major.minor.micro` here would 404 on non-Windows targets whenever it
+/// doesn't happen to match the pinned release exactly.
+async fn default_python_version() -> anyhow::Result<PyVerTuple> {
+    let out = Command::new("python")
+        .args(&[
+            "-c",
+            "import sys; print('.'.join(str(x) for x in sys.version_info[:2]))",
+        ])
+        .output()
+        .await?;
+
+    let ver_str = String::from_utf8_lossy(&out.stdout);
+
+    python_version_from_str(&ver_str)
+}
+
+/// Parse a version specifier, which may be a full `1.2.3`, or a partial
+/// `1.2`/`1` that is resolved against [`KNOWN_VERSIONS`].
+pub fn python_version_from_str(s: &str) -> anyhow::Result<PyVerTuple> {
+    let parts = s.trim().split('.').collect::<Vec<_>>();
+
+    match parts.as_slice() {
+        &[major, minor, micro] => Ok((major.parse()?, minor.parse()?, micro.parse()?)),
+        &[major, minor] => {
+            let major: u16 = major.parse()?;
+            let minor: u16 = minor.parse()?;
+            latest_known(|(maj, min, _)| *maj == major && *min == minor)
+                .ok_or_else(|| anyhow!("no known micro release for Python {major}.{minor}"))
+        }
+        &[major] => {
+            let major: u16 = major.parse()?;
+            latest_known(|(maj, _, _)| *maj == major)
+                .ok_or_else(|| anyhow!("no known release for Python {major}"))
+        }
+        _ => bail!("Version must be of the format: 1, 1.2, or 1.2.3"),
+    }
+}
+
+fn latest_known(pred: impl Fn(&PyVerTuple) -> bool) -> Option<PyVerTuple> {
+    KNOWN_VERSIONS.iter().rev().find(|v| pred(v)).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_full_version() {
+        assert_eq!(python_version_from_str("3.11.8").unwrap(), (3, 11, 8));
+    }
+
+    #[test]
+    fn resolves_partial_minor_to_latest_known_micro() {
+        assert_eq!(python_version_from_str("3.12").unwrap(), (3, 12, 2));
+    }
+
+    #[test]
+    fn resolves_partial_major_to_latest_known_release() {
+        assert_eq!(python_version_from_str("3").unwrap(), (3, 12, 2));
+    }
+
+    #[test]
+    fn rejects_unknown_minor() {
+        assert!(python_version_from_str("3.99").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_spec() {
+        assert!(python_version_from_str("3.9.1.2").is_err());
+        assert!(python_version_from_str("not-a-version").is_err());
+    }
+}